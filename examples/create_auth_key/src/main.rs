@@ -7,6 +7,7 @@ extern crate log;
 extern crate telegram;
 
 use telegram::{schema, Client, Request, Response};
+use telegram::transport::HttpTransport;
 
 fn main() {
     run().unwrap();
@@ -39,9 +40,11 @@ fn run() -> telegram::error::Result<()> {
     // [DEBUG] Step
     println!(" - Send {}\n", "http://149.154.167.50:443/api");
 
-    let mut client = Client::new()?;
+    let mut transport = HttpTransport::new("http://149.154.167.50:443/api".parse()?)?;
+
+    let mut client = Client::new();
     client
-        .send(req, |data: Response<schema::mtproto::ResPQ>| {
+        .send(&mut transport, req, |data: Response<schema::mtproto::ResPQ>| {
             // [DEBUG] Step
             println!(" - Response");
             pprint(&data.to_bytes().unwrap());
@@ -1,51 +1,143 @@
-use futures::{Future, Stream};
-use hyper::client::HttpConnector;
-use hyper::{self, Body};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use serde_mtproto::Identifiable;
-use tokio::reactor::Core;
 
+use compression;
 use error;
+use limit::{Limit, LimitedReader};
 use request::Request;
 use response::Response;
+use session::Session;
+use transport::Transport;
 
-pub struct Client {
-    core: Core,
-    http_client: hyper::Client<HttpConnector, Body>,
+/// Per-call configuration for [`Client::send_with_options`].
+#[derive(Debug, Clone, Copy)]
+pub struct SendOptions {
+    /// Byte/recursion budget enforced while deserializing the response.
+    pub limit: Limit,
+
+    /// Requests whose serialized body is larger than this many bytes
+    /// are wrapped in `gzip_packed` before being sent. `None` (the
+    /// default) disables compression.
+    pub compression_threshold: Option<usize>,
+}
+
+impl Default for SendOptions {
+    #[inline]
+    fn default() -> SendOptions {
+        SendOptions {
+            limit: Limit::default(),
+            compression_threshold: None,
+        }
+    }
 }
 
+/// Sends `Request`s and reads back `Response`s over any [`Transport`].
+pub struct Client;
+
 impl Client {
     /// Create a new Telegram client.
     #[inline]
-    pub fn new() -> error::Result<Client> {
-        let core = Core::new()?;
-        let http_client = hyper::Client::new(&core.handle());
-
-        Ok(Client {
-            core: core,
-            http_client: http_client,
-        })
+    pub fn new() -> Client {
+        Client
+    }
+
+    /// Send a constructed request over the given transport, using the
+    /// default [`SendOptions`] (bounded deserialization, no
+    /// compression).
+    #[inline]
+    pub fn send<Tr, F, T, U, R>(
+        &mut self,
+        transport: &mut Tr,
+        req: Request<T>,
+        on_receive_handler: F,
+    ) -> error::Result<R>
+    where
+        Tr: Transport,
+        F: FnOnce(Response<U>) -> R,
+        T: Serialize + Identifiable,
+        U: 'static + DeserializeOwned + Identifiable,
+    {
+        self.send_with_options(transport, req, SendOptions::default(), on_receive_handler)
+    }
+
+    /// Send a constructed request over the given transport, applying
+    /// the given [`SendOptions`].
+    pub fn send_with_options<Tr, F, T, U, R>(
+        &mut self,
+        transport: &mut Tr,
+        req: Request<T>,
+        options: SendOptions,
+        on_receive_handler: F,
+    ) -> error::Result<R>
+    where
+        Tr: Transport,
+        F: FnOnce(Response<U>) -> R,
+        T: Serialize + Identifiable,
+        U: 'static + DeserializeOwned + Identifiable,
+    {
+        let body = req.to_bytes()?;
+
+        let body = match options.compression_threshold {
+            Some(threshold) => compression::compress_if_large(&body, threshold)?,
+            None => body,
+        };
+
+        transport.send_packet(&body)?;
+        let data = transport.recv_packet()?;
+
+        let data = match compression::maybe_decompress(&data)? {
+            Some(inflated) => inflated,
+            None => data,
+        };
+
+        let mut limit = options.limit;
+        let response = Response::from_reader(&mut LimitedReader::new(&*data, &mut limit))?;
+
+        Ok(on_receive_handler(response))
     }
 
-    // Send a constructed request using this Client.
-    pub fn send<F, T, U, R>(&mut self, req: Request<T>, on_receive_handler: F) -> error::Result<R>
+    /// Send a constructed request over the given transport as an
+    /// AES-256-IGE encrypted message, using the auth key held by
+    /// `session`. This is what makes authenticated API calls (as
+    /// opposed to the plaintext key-exchange calls) possible.
+    pub fn send_encrypted<Tr, F, T, U, R>(
+        &mut self,
+        transport: &mut Tr,
+        session: &Session,
+        req: Request<T>,
+        options: SendOptions,
+        on_receive_handler: F,
+    ) -> error::Result<R>
     where
+        Tr: Transport,
         F: FnOnce(Response<U>) -> R,
         T: Serialize + Identifiable,
         U: 'static + DeserializeOwned + Identifiable,
     {
-        let http_request = req.to_http_request()?;
-
-        let promise = Box::new(
-            self.http_client
-                .request(http_request)
-                .and_then(|res| res.body().concat2())
-                .map(|data| Response::from_reader(&*data))
-                .flatten()
-                .map_err(|err| err.into()),
-        ).map(on_receive_handler);
-
-        self.core.run(promise)
+        let body = req.to_bytes()?;
+
+        let body = match options.compression_threshold {
+            Some(threshold) => compression::compress_if_large(&body, threshold)?,
+            None => body,
+        };
+
+        // Session::encrypt pads internally, after computing msg_key
+        // over this still-unpadded body.
+        let encrypted = session.encrypt(&body)?;
+        transport.send_packet(&encrypted)?;
+
+        let message = transport.recv_packet()?;
+        let decrypted = session.decrypt(&message)?;
+
+        let decrypted = match compression::maybe_decompress(&decrypted)? {
+            Some(inflated) => inflated,
+            None => decrypted,
+        };
+
+        let mut limit = options.limit;
+        let response = Response::from_reader(&mut LimitedReader::new(&*decrypted, &mut limit))?;
+
+        Ok(on_receive_handler(response))
     }
 }
@@ -0,0 +1,130 @@
+use std::io::{Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+use de::BoundedRead;
+use error;
+
+/// Constructor id for `gzip_packed`.
+pub const GZIP_PACKED_ID: u32 = 0x3072cfa1;
+
+/// Write `bytes` using the same length-prefix and 0-3 byte padding
+/// rules as the `String` `Serialize` impl (`gzip_packed`'s payload is
+/// itself a TL `string`, just not necessarily valid UTF-8).
+fn write_tl_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) -> error::Result<()> {
+    let len = bytes.len();
+
+    if len <= 253 {
+        buffer.push(len as u8);
+    } else {
+        buffer.push(254);
+        buffer.write_uint::<LittleEndian>(len as u64, 3)?;
+    }
+
+    buffer.extend(bytes);
+
+    let rem = len % 4;
+    if rem > 0 {
+        for _ in 0..(4 - rem) {
+            buffer.push(0);
+        }
+    }
+
+    Ok(())
+}
+
+/// Inverse of `write_tl_bytes`; see `Deserialize for String` for the
+/// same decoding rules.
+fn read_tl_bytes(reader: &mut impl BoundedRead) -> error::Result<Vec<u8>> {
+    let mut len_byte = [0u8; 1];
+    reader.read_exact(&mut len_byte)?;
+
+    let len = if len_byte[0] == 254 {
+        reader.read_uint::<LittleEndian>(3)? as usize
+    } else {
+        len_byte[0] as usize
+    };
+
+    reader.charge(len as u64)?;
+
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+
+    let rem = len % 4;
+    if rem > 0 {
+        let mut padding = [0u8; 3];
+        reader.read_exact(&mut padding[..4 - rem])?;
+    }
+
+    Ok(bytes)
+}
+
+/// Wrap `body` in a `gzip_packed` container when it is larger than
+/// `threshold`, otherwise return it unchanged.
+pub fn compress_if_large(body: &[u8], threshold: usize) -> error::Result<Vec<u8>> {
+    if body.len() <= threshold {
+        return Ok(body.to_vec());
+    }
+
+    let mut gzipped = Vec::new();
+    {
+        let mut encoder = GzEncoder::new(&mut gzipped, Compression::default());
+        encoder.write_all(body)?;
+    }
+
+    let mut packed = Vec::new();
+    packed.write_u32::<LittleEndian>(GZIP_PACKED_ID)?;
+    write_tl_bytes(&mut packed, &gzipped)?;
+
+    Ok(packed)
+}
+
+/// If `body` starts with the `gzip_packed` constructor, gunzip the
+/// contained TL string and return the inflated bytes. Otherwise `None`
+/// is returned, so the caller can fall back to treating `body` as an
+/// uncompressed payload.
+///
+/// Used by `Client::send_with_options` and `Client::send_encrypted` to
+/// transparently unwrap `gzip_packed` responses before handing them to
+/// `Response::from_reader`.
+pub fn maybe_decompress(body: &[u8]) -> error::Result<Option<Vec<u8>>> {
+    if body.len() < 4 || (&body[..4]).read_u32::<LittleEndian>()? != GZIP_PACKED_ID {
+        return Ok(None);
+    }
+
+    let mut reader = &body[4..];
+    let gzipped = read_tl_bytes(&mut reader)?;
+
+    let mut inflated = Vec::new();
+    GzDecoder::new(&gzipped[..])?.read_to_end(&mut inflated)?;
+
+    Ok(Some(inflated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress_if_large, maybe_decompress};
+
+    #[test]
+    fn below_threshold_is_left_untouched() {
+        let body = vec![1u8, 2, 3, 4];
+
+        let packed = compress_if_large(&body, 16).unwrap();
+        assert_eq!(packed, body);
+        assert!(maybe_decompress(&packed).unwrap().is_none());
+    }
+
+    #[test]
+    fn above_threshold_round_trips() {
+        let body: Vec<u8> = (0..2048).map(|i| (i % 251) as u8).collect();
+
+        let packed = compress_if_large(&body, 16).unwrap();
+        assert_ne!(packed, body);
+
+        let inflated = maybe_decompress(&packed).unwrap().unwrap();
+        assert_eq!(inflated, body);
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::{self, Read};
+use std::mem;
+
+use byteorder::{ReadBytesExt, BigEndian, LittleEndian};
+use extprim::i128::i128;
+use extprim::u128::u128;
+
+use error;
+
+macro_rules! impl_deserialize {
+    ($type:path, $read:path) => {
+        impl Deserialize for $type {
+            #[inline]
+            fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+                Ok($read(reader)?)
+            }
+        }
+    };
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// A [`Read`] that can be asked, up front, whether it is willing to
+/// hand out `len` more bytes, and that tracks how deeply nested the
+/// value currently being read is.
+///
+/// [`Deserialize`] impls that allocate based on a length prefix read
+/// off the wire (`String`, `Vec<T>`) call `charge` before allocating,
+/// so a malicious length is rejected instead of driving an unbounded
+/// allocation; see [`LimitedReader`](::limit::LimitedReader) for the
+/// bounded implementation.
+pub trait BoundedRead: Read {
+    /// Charge `len` bytes against whatever budget is backing this
+    /// reader, failing if it would be exceeded.
+    fn charge(&mut self, len: u64) -> error::Result<()>;
+
+    /// Account for entering one more level of nested value, failing if
+    /// the configured recursion depth would be exceeded. Readers with
+    /// no limit configured accept unbounded nesting.
+    fn enter_nested(&mut self) -> error::Result<()> {
+        Ok(())
+    }
+
+    /// Leave a level entered via `enter_nested`.
+    fn leave_nested(&mut self) {}
+}
+
+impl<'a> BoundedRead for &'a [u8] {
+    #[inline]
+    fn charge(&mut self, _len: u64) -> error::Result<()> {
+        Ok(())
+    }
+}
+
+/// Symmetric counterpart to [`Serialize`](::ser::Serialize): read an
+/// MTProto-encoded value back out of a reader.
+pub trait Deserialize: Sized {
+    /// Deserialize from the passed reader.
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self>;
+}
+
+impl Deserialize for bool {
+    #[inline]
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        match reader.read_i32::<LittleEndian>()? {
+            -1720552011 => Ok(true),
+            -1132882121 => Ok(false),
+            _ => Err(invalid_data("invalid bool constructor").into()),
+        }
+    }
+}
+
+impl Deserialize for i8 {
+    #[inline]
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        Ok(reader.read_i8()?)
+    }
+}
+
+impl Deserialize for u8 {
+    #[inline]
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        Ok(reader.read_u8()?)
+    }
+}
+
+impl_deserialize!(i16, ReadBytesExt::read_i16<LittleEndian>);
+impl_deserialize!(i32, ReadBytesExt::read_i32<LittleEndian>);
+impl_deserialize!(i64, ReadBytesExt::read_i64<LittleEndian>);
+
+impl_deserialize!(u16, ReadBytesExt::read_u16<LittleEndian>);
+impl_deserialize!(u32, ReadBytesExt::read_u32<LittleEndian>);
+impl_deserialize!(u64, ReadBytesExt::read_u64<LittleEndian>);
+
+impl_deserialize!(f32, ReadBytesExt::read_f32<LittleEndian>);
+impl_deserialize!(f64, ReadBytesExt::read_f64<LittleEndian>);
+
+impl Deserialize for i128 {
+    #[inline]
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        Ok(u128::deserialize_from(reader)?.as_i128())
+    }
+}
+
+impl Deserialize for u128 {
+    #[inline]
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        // Inverse of the Serialize impl: two big-endian u64 halves,
+        // high then low (see the TODO over there about the
+        // alternative 4*int interpretation).
+        let high = reader.read_u64::<BigEndian>()?;
+        let low = reader.read_u64::<BigEndian>()?;
+
+        Ok(u128::from_parts(high, low))
+    }
+}
+
+impl Deserialize for (i128, i128) {
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        // Int256 is serialized as big-endian relative to int128, i.e.
+        // `self.1` followed by `self.0`; read them back in that order.
+        let snd = i128::deserialize_from(reader)?;
+        let fst = i128::deserialize_from(reader)?;
+
+        Ok((fst, snd))
+    }
+}
+
+impl Deserialize for String {
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        let mut len_byte = [0u8; 1];
+        reader.read_exact(&mut len_byte)?;
+
+        let len = if len_byte[0] == 254 {
+            reader.read_uint::<LittleEndian>(3)? as usize
+        } else {
+            len_byte[0] as usize
+        };
+
+        reader.charge(len as u64)?;
+
+        let mut bytes = vec![0u8; len];
+        reader.read_exact(&mut bytes)?;
+
+        // Inverse of the Serialize impl: the padding is sized off the
+        // content length alone, not the length-prefix length.
+        let rem = len % 4;
+        if rem > 0 {
+            let mut padding = [0u8; 3];
+            reader.read_exact(&mut padding[..4 - rem])?;
+        }
+
+        String::from_utf8(bytes).map_err(|err| invalid_data_utf8(err).into())
+    }
+}
+
+fn invalid_data_utf8(err: ::std::string::FromUtf8Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+impl<T: Deserialize> Deserialize for Vec<T> {
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        let id = reader.read_u32::<LittleEndian>()?;
+        if id != 0x1cb5c415u32 {
+            return Err(invalid_data("unexpected Vec constructor").into());
+        }
+
+        let len = reader.read_u32::<LittleEndian>()?;
+
+        // Charge the bytes `Vec::with_capacity` below is about to
+        // allocate, not just the element count, or a declared count
+        // of a large T sails under the byte budget while still
+        // driving a huge allocation.
+        let declared_bytes = u64::from(len)
+            .checked_mul(mem::size_of::<T>() as u64)
+            .ok_or_else(|| invalid_data("declared Vec size overflows"))?;
+        reader.charge(declared_bytes)?;
+
+        reader.enter_nested()?;
+        let mut elements = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            elements.push(T::deserialize_from(reader)?);
+        }
+        reader.leave_nested();
+
+        Ok(elements)
+    }
+}
+
+// No Deserialize impl for Box<Any>: once the concrete type has been
+// erased there is nothing left to downcast *into* on the read side.
@@ -0,0 +1,153 @@
+use std::io;
+
+use crypto::aessafe::{AesSafe256Decryptor, AesSafe256Encryptor};
+use crypto::symmetriccipher::{BlockDecryptor, BlockEncryptor};
+
+use error;
+
+const BLOCK_SIZE: usize = 16;
+
+fn xor_block(a: &[u8], b: &[u8], out: &mut [u8]) {
+    for i in 0..BLOCK_SIZE {
+        out[i] = a[i] ^ b[i];
+    }
+}
+
+fn not_block_aligned(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Encrypt `plaintext` with AES-256 in Infinite Garble Extension
+/// (IGE) mode.
+///
+/// `key` is the 32-byte AES-256 key. `iv` is the 32-byte IGE IV,
+/// split into the two 16-byte halves `iv1`/`iv2` used as the initial
+/// ciphertext/plaintext feedback (`c_prev`/`p_prev` respectively).
+/// `plaintext` must already be a multiple of the 16-byte AES block
+/// size.
+pub fn ige_encrypt(key: &[u8; 32], iv: &[u8; 32], plaintext: &[u8]) -> error::Result<Vec<u8>> {
+    if plaintext.len() % BLOCK_SIZE != 0 {
+        return Err(not_block_aligned("IGE plaintext must be a multiple of 16 bytes").into());
+    }
+
+    let encryptor = AesSafe256Encryptor::new(key);
+
+    let mut c_prev = [0u8; BLOCK_SIZE];
+    c_prev.copy_from_slice(&iv[..BLOCK_SIZE]);
+    let mut p_prev = [0u8; BLOCK_SIZE];
+    p_prev.copy_from_slice(&iv[BLOCK_SIZE..]);
+
+    let mut ciphertext = Vec::with_capacity(plaintext.len());
+
+    for block in plaintext.chunks(BLOCK_SIZE) {
+        let mut xored = [0u8; BLOCK_SIZE];
+        xor_block(block, &c_prev, &mut xored);
+
+        let mut encrypted = [0u8; BLOCK_SIZE];
+        encryptor.encrypt_block(&xored, &mut encrypted);
+
+        let mut c = [0u8; BLOCK_SIZE];
+        xor_block(&encrypted, &p_prev, &mut c);
+
+        ciphertext.extend_from_slice(&c);
+
+        p_prev.copy_from_slice(block);
+        c_prev.copy_from_slice(&c);
+    }
+
+    Ok(ciphertext)
+}
+
+/// Symmetric inverse of [`ige_encrypt`].
+pub fn ige_decrypt(key: &[u8; 32], iv: &[u8; 32], ciphertext: &[u8]) -> error::Result<Vec<u8>> {
+    if ciphertext.len() % BLOCK_SIZE != 0 {
+        return Err(not_block_aligned("IGE ciphertext must be a multiple of 16 bytes").into());
+    }
+
+    let decryptor = AesSafe256Decryptor::new(key);
+
+    let mut c_prev = [0u8; BLOCK_SIZE];
+    c_prev.copy_from_slice(&iv[..BLOCK_SIZE]);
+    let mut p_prev = [0u8; BLOCK_SIZE];
+    p_prev.copy_from_slice(&iv[BLOCK_SIZE..]);
+
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+
+    for block in ciphertext.chunks(BLOCK_SIZE) {
+        let mut xored = [0u8; BLOCK_SIZE];
+        xor_block(block, &p_prev, &mut xored);
+
+        let mut decrypted = [0u8; BLOCK_SIZE];
+        decryptor.decrypt_block(&xored, &mut decrypted);
+
+        let mut p = [0u8; BLOCK_SIZE];
+        xor_block(&decrypted, &c_prev, &mut p);
+
+        plaintext.extend_from_slice(&p);
+
+        c_prev.copy_from_slice(block);
+        p_prev.copy_from_slice(&p);
+    }
+
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ige_decrypt, ige_encrypt};
+
+    fn key() -> [u8; 32] {
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        key
+    }
+
+    fn iv() -> [u8; 32] {
+        let mut iv = [0u8; 32];
+        for (i, byte) in iv.iter_mut().enumerate() {
+            *byte = (32 + i) as u8;
+        }
+        iv
+    }
+
+    #[test]
+    fn round_trips() {
+        let plaintext: Vec<u8> = (0..64).map(|i| ((i * 7 + 3) % 256) as u8).collect();
+
+        let ciphertext = ige_encrypt(&key(), &iv(), &plaintext).unwrap();
+        assert_ne!(ciphertext, plaintext);
+
+        let decrypted = ige_decrypt(&key(), &iv(), &ciphertext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    /// `key = 00 01 .. 1F`, `iv = 20 21 .. 3F`, `plaintext[i] = (i*7+3)
+    /// % 256` for two blocks, cross-checked against an independent
+    /// Python implementation of the same algorithm built on
+    /// `cryptography`'s AES-ECB (i.e. raw AES block encrypt/decrypt).
+    #[test]
+    fn matches_known_answer_vector() {
+        let plaintext: Vec<u8> = (0..32).map(|i| ((i * 7 + 3) % 256) as u8).collect();
+
+        let ciphertext = ige_encrypt(&key(), &iv(), &plaintext).unwrap();
+
+        assert_eq!(
+            ciphertext,
+            vec![
+                0x62, 0xdd, 0xe7, 0xa4, 0x35, 0xaf, 0xbc, 0x7b, 0xc6, 0xb1, 0x6a, 0x3b, 0x7d,
+                0xe4, 0x2d, 0x3e, 0xae, 0xb0, 0x24, 0x59, 0xb4, 0x57, 0xbf, 0x8e, 0xa3, 0x5b,
+                0x5a, 0x86, 0xe0, 0xfb, 0x4e, 0x58,
+            ]
+        );
+
+        assert_eq!(ige_decrypt(&key(), &iv(), &ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn rejects_non_block_aligned_input() {
+        assert!(ige_encrypt(&key(), &iv(), &[0u8; 15]).is_err());
+        assert!(ige_decrypt(&key(), &iv(), &[0u8; 17]).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use extprim::u128::u128;
+
+use de::{BoundedRead, Deserialize};
+use error;
+use ser::Serialize;
+
+/// TL `int128`, pinned to the big-endian-u64-halves layout the bare
+/// `u128` impl in `ser`/`de` already uses and that was validated
+/// against https://core.telegram.org/mtproto/samples-auth_key (see
+/// the TODO left on that impl questioning it against the schema's
+/// `int128 4*[ int ] = Int128;` definition).
+///
+/// Intended to replace the bare `i128`/`u128` used directly for
+/// `nonce`/`server_nonce`/`new_nonce`-shaped fields once those live in
+/// a self-documenting type instead of a primitive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Int128(pub u128);
+
+impl Int128 {
+    #[inline]
+    pub fn from_parts(high: u64, low: u64) -> Int128 {
+        Int128(u128::from_parts(high, low))
+    }
+
+    /// Builds an `Int128` from the `int128 4*[ int ] = Int128;` reading
+    /// of the schema: four 32-bit words, most significant first. Four
+    /// big-endian words in this order are the same bytes as the two
+    /// big-endian 64-bit halves of [`Int128::from_parts`], so the result
+    /// equals `from_parts((w0 << 32) | w1, (w2 << 32) | w3)` and
+    /// serializes identically; no separate wire encoding is provided.
+    pub fn from_be_u32_words(words: [u32; 4]) -> Int128 {
+        let high = (u64::from(words[0]) << 32) | u64::from(words[1]);
+        let low = (u64::from(words[2]) << 32) | u64::from(words[3]);
+
+        Int128::from_parts(high, low)
+    }
+}
+
+impl Serialize for Int128 {
+    #[inline]
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> error::Result<()> {
+        buffer.write_u64::<BigEndian>(self.0.high64())?;
+        buffer.write_u64::<BigEndian>(self.0.low64())?;
+
+        Ok(())
+    }
+}
+
+impl Deserialize for Int128 {
+    #[inline]
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        let high = reader.read_u64::<BigEndian>()?;
+        let low = reader.read_u64::<BigEndian>()?;
+
+        Ok(Int128::from_parts(high, low))
+    }
+}
+
+/// TL `int256`, stored as its two `Int128` halves in wire order
+/// (`high` first, `low` second) instead of the reversed-element
+/// `(i128, i128)` tuple this used to stand in for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Int256 {
+    pub high: Int128,
+    pub low: Int128,
+}
+
+impl Int256 {
+    #[inline]
+    pub fn new(high: Int128, low: Int128) -> Int256 {
+        Int256 {
+            high: high,
+            low: low,
+        }
+    }
+
+    /// Builds an `Int256` from the `int256 8*[ int ] = Int256;` reading
+    /// of the schema: eight 32-bit words, most significant first.
+    /// Mirrors [`Int128::from_be_u32_words`]: the result equals the
+    /// value [`Int256::new`] builds from the same words and serializes
+    /// identically; no separate wire encoding is provided.
+    pub fn from_be_u32_words(words: [u32; 8]) -> Int256 {
+        let high = Int128::from_be_u32_words([words[0], words[1], words[2], words[3]]);
+        let low = Int128::from_be_u32_words([words[4], words[5], words[6], words[7]]);
+
+        Int256::new(high, low)
+    }
+}
+
+impl Serialize for Int256 {
+    fn serialize_to(&self, buffer: &mut Vec<u8>) -> error::Result<()> {
+        self.high.serialize_to(buffer)?;
+        self.low.serialize_to(buffer)?;
+
+        Ok(())
+    }
+}
+
+impl Deserialize for Int256 {
+    fn deserialize_from(reader: &mut impl BoundedRead) -> error::Result<Self> {
+        let high = Int128::deserialize_from(reader)?;
+        let low = Int128::deserialize_from(reader)?;
+
+        Ok(Int256::new(high, low))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de::Deserialize;
+    use ser::Serialize;
+
+    use super::{Int128, Int256};
+
+    #[test]
+    fn int128_matches_known_wire_bytes() {
+        // The req_pq nonce from
+        // https://core.telegram.org/mtproto/samples-auth_key, also
+        // used verbatim in examples/create_auth_key.
+        let value = Int128::from_parts(0x3E0549828CCA27E9, 0x66B301A48FECE2FC);
+
+        let mut buffer = Vec::new();
+        value.serialize_to(&mut buffer).unwrap();
+        assert_eq!(
+            buffer,
+            vec![
+                0x3E, 0x05, 0x49, 0x82, 0x8C, 0xCA, 0x27, 0xE9, 0x66, 0xB3, 0x01, 0xA4, 0x8F,
+                0xEC, 0xE2, 0xFC,
+            ]
+        );
+
+        let mut reader = &buffer[..];
+        assert_eq!(Int128::deserialize_from(&mut reader).unwrap(), value);
+    }
+
+    #[test]
+    fn int128_four_word_constructor_matches_canonical_layout() {
+        let canonical = Int128::from_parts(0x3E0549828CCA27E9, 0x66B301A48FECE2FC);
+        let four_words =
+            Int128::from_be_u32_words([0x3E054982, 0x8CCA27E9, 0x66B301A4, 0x8FECE2FC]);
+
+        assert_eq!(canonical, four_words);
+    }
+
+    #[test]
+    fn int256_matches_known_wire_bytes() {
+        let value = Int256::new(Int128::from_parts(1, 2), Int128::from_parts(3, 4));
+
+        let mut buffer = Vec::new();
+        value.serialize_to(&mut buffer).unwrap();
+        assert_eq!(
+            buffer,
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0,
+                0, 0, 0, 0, 4,
+            ]
+        );
+
+        let mut reader = &buffer[..];
+        assert_eq!(Int256::deserialize_from(&mut reader).unwrap(), value);
+    }
+
+    #[test]
+    fn int256_eight_word_constructor_matches_canonical_layout() {
+        let canonical = Int256::new(Int128::from_parts(1, 2), Int128::from_parts(3, 4));
+        let eight_words = Int256::from_be_u32_words([0, 1, 0, 2, 0, 3, 0, 4]);
+
+        assert_eq!(canonical, eight_words);
+    }
+
+    /// `Int256` is meant as a drop-in, self-documenting replacement
+    /// for the bare `(i128, i128)` tuple: the old impl writes `self.1`
+    /// then `self.0`, i.e. `self.1` is the high 128 bits and `self.0`
+    /// the low. Pin that the two encode identically for the same
+    /// logical value, so a migration from the tuple to `Int256`
+    /// cannot silently flip byte order.
+    #[test]
+    fn matches_bare_tuple_encoding() {
+        let high = Int128::from_parts(1, 2);
+        let low = Int128::from_parts(3, 4);
+
+        let tuple = (low.0.as_i128(), high.0.as_i128());
+
+        let mut tuple_buffer = Vec::new();
+        tuple.serialize_to(&mut tuple_buffer).unwrap();
+
+        let mut int256_buffer = Vec::new();
+        Int256::new(high, low)
+            .serialize_to(&mut int256_buffer)
+            .unwrap();
+
+        assert_eq!(tuple_buffer, int256_buffer);
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::{self, Read};
+
+use de::BoundedRead;
+use error;
+
+/// Byte and recursion-depth budget enforced while deserializing an
+/// attacker-influenced MTProto response.
+///
+/// Mirrors bincode's `config::Bounded` limit: every allocation implied
+/// by a length prefix read off the wire (the `String`/`Vec<T>` length
+/// prefixes) is charged against `remaining` *before* it is trusted, so
+/// a malicious length is rejected up front instead of driving an
+/// unbounded allocation, and entering a nested value is charged
+/// against `max_depth` to rule out unbounded recursion.
+#[derive(Debug, Clone, Copy)]
+pub struct Limit {
+    remaining: u64,
+    depth: u32,
+    max_depth: u32,
+}
+
+impl Limit {
+    #[inline]
+    pub fn new(byte_limit: u64, max_depth: u32) -> Limit {
+        Limit {
+            remaining: byte_limit,
+            depth: 0,
+            max_depth: max_depth,
+        }
+    }
+}
+
+impl Default for Limit {
+    /// 16 MiB of payload and 64 levels of nesting, which comfortably
+    /// covers any legitimate MTProto response.
+    #[inline]
+    fn default() -> Limit {
+        Limit::new(16 * 1024 * 1024, 64)
+    }
+}
+
+/// A [`Read`] adapter that charges every declared allocation length
+/// against a [`Limit`], for use as the reader passed to
+/// [`Deserialize::deserialize_from`](::de::Deserialize::deserialize_from).
+pub struct LimitedReader<'a, R> {
+    inner: R,
+    limit: &'a mut Limit,
+}
+
+impl<'a, R: Read> LimitedReader<'a, R> {
+    #[inline]
+    pub fn new(inner: R, limit: &'a mut Limit) -> LimitedReader<'a, R> {
+        LimitedReader {
+            inner: inner,
+            limit: limit,
+        }
+    }
+}
+
+impl<'a, R: Read> Read for LimitedReader<'a, R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<'a, R: Read> BoundedRead for LimitedReader<'a, R> {
+    fn charge(&mut self, len: u64) -> error::Result<()> {
+        match self.limit.remaining.checked_sub(len) {
+            Some(remaining) => {
+                self.limit.remaining = remaining;
+                Ok(())
+            }
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, "byte limit exceeded").into()),
+        }
+    }
+
+    fn enter_nested(&mut self) -> error::Result<()> {
+        if self.limit.depth >= self.limit.max_depth {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidData, "recursion limit exceeded").into(),
+            );
+        }
+
+        self.limit.depth += 1;
+
+        Ok(())
+    }
+
+    fn leave_nested(&mut self) {
+        self.limit.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de::Deserialize;
+
+    use super::{Limit, LimitedReader};
+
+    #[test]
+    fn oversized_string_length_is_rejected_up_front() {
+        // Declares a string longer than the configured budget and
+        // provides none of the content; charge() must reject this
+        // before any allocation happens, so the error must come back
+        // immediately rather than from read_exact() hitting EOF.
+        let buffer = [254u8, 0x00, 0x40, 0x0c];
+
+        let mut limit = Limit::new(1024, 64);
+        let mut reader = LimitedReader::new(&buffer[..], &mut limit);
+
+        assert!(String::deserialize_from(&mut reader).is_err());
+    }
+
+    #[test]
+    fn truncated_vec_is_rejected_not_panicking() {
+        // Claims 0x1cb5c415 / 1000 elements but the buffer ends right
+        // after the count.
+        let buffer = [0x15u8, 0xc4, 0xb5, 0x1c, 0xe8, 0x03, 0x00, 0x00];
+
+        let mut limit = Limit::default();
+        let mut reader = LimitedReader::new(&buffer[..], &mut limit);
+
+        assert!(Vec::<i32>::deserialize_from(&mut reader).is_err());
+    }
+}
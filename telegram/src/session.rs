@@ -0,0 +1,251 @@
+use std::io;
+
+use crypto::digest::Digest;
+use crypto::sha1::Sha1;
+
+use error;
+use ige;
+
+/// Which direction a message is travelling; selects the `x` offset
+/// used in the MTProto key-derivation formula below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+impl Direction {
+    fn offset(self) -> usize {
+        match self {
+            Direction::ClientToServer => 0,
+            Direction::ServerToClient => 8,
+        }
+    }
+}
+
+/// An established MTProto auth key, used to encrypt/decrypt message
+/// containers once the key exchange (`req_pq` / ... / `set_client_DH_params`)
+/// has completed.
+pub struct Session {
+    auth_key: [u8; 256],
+}
+
+impl Session {
+    #[inline]
+    pub fn new(auth_key: [u8; 256]) -> Session {
+        Session { auth_key: auth_key }
+    }
+
+    /// Encrypt `payload` (the serialized, *unpadded* MTProto message
+    /// container) for sending to the server, returning `auth_key_id ||
+    /// msg_key || ciphertext`, ready to hand to a
+    /// [`Transport`](::transport::Transport).
+    ///
+    /// Per MTProto, `msg_key` is derived from the message data before
+    /// padding, so `payload` must be passed in unpadded here; padding
+    /// to the 16-byte IGE block size is applied internally, after
+    /// `msg_key` has already been computed.
+    pub fn encrypt(&self, payload: &[u8]) -> error::Result<Vec<u8>> {
+        let msg_key = self.msg_key(payload);
+        let (key, iv) = self.derive_key_iv(&msg_key, Direction::ClientToServer);
+
+        let ciphertext = ige::ige_encrypt(&key, &iv, &pad_to_block(payload))?;
+
+        let mut out = Vec::with_capacity(8 + 16 + ciphertext.len());
+        out.extend_from_slice(&self.auth_key_id());
+        out.extend_from_slice(&msg_key);
+        out.extend_from_slice(&ciphertext);
+
+        Ok(out)
+    }
+
+    /// Decrypt `message` (`auth_key_id || msg_key || ciphertext`) as
+    /// received from the server, returning the plaintext message
+    /// container.
+    pub fn decrypt(&self, message: &[u8]) -> error::Result<Vec<u8>> {
+        if message.len() < 24 {
+            return Err(invalid_data("encrypted message shorter than its header").into());
+        }
+
+        let mut msg_key = [0u8; 16];
+        msg_key.copy_from_slice(&message[8..24]);
+
+        let (key, iv) = self.derive_key_iv(&msg_key, Direction::ServerToClient);
+
+        ige::ige_decrypt(&key, &iv, &message[24..])
+    }
+
+    /// Lower 64 bits of `SHA1(auth_key)`, identifying this key to the
+    /// server.
+    fn auth_key_id(&self) -> [u8; 8] {
+        let digest = sha1_of(&[&self.auth_key]);
+
+        let mut id = [0u8; 8];
+        id.copy_from_slice(&digest[12..20]);
+        id
+    }
+
+    fn msg_key(&self, payload: &[u8]) -> [u8; 16] {
+        let digest = sha1_of(&[payload]);
+
+        let mut msg_key = [0u8; 16];
+        msg_key.copy_from_slice(&digest[4..20]);
+        msg_key
+    }
+
+    /// The MTProto 1.0 `aes_key`/`aes_iv` derivation:
+    ///
+    /// ```text
+    /// sha1_a = SHA1(msg_key + auth_key[x : x+32])
+    /// sha1_b = SHA1(auth_key[32+x : 48+x] + msg_key + auth_key[48+x : 64+x])
+    /// sha1_c = SHA1(auth_key[64+x : 96+x] + msg_key)
+    /// sha1_d = SHA1(msg_key + auth_key[96+x : 128+x])
+    ///
+    /// aes_key = sha1_a[0:8] + sha1_b[8:20] + sha1_c[4:16]
+    /// aes_iv  = sha1_a[8:20] + sha1_b[0:8] + sha1_c[16:20] + sha1_d[0:8]
+    /// ```
+    fn derive_key_iv(&self, msg_key: &[u8; 16], direction: Direction) -> ([u8; 32], [u8; 32]) {
+        let x = direction.offset();
+        let auth_key = &self.auth_key;
+
+        let sha1_a = sha1_of(&[msg_key, &auth_key[x..x + 32]]);
+        let sha1_b = sha1_of(&[
+            &auth_key[32 + x..48 + x],
+            msg_key,
+            &auth_key[48 + x..64 + x],
+        ]);
+        let sha1_c = sha1_of(&[&auth_key[64 + x..96 + x], msg_key]);
+        let sha1_d = sha1_of(&[msg_key, &auth_key[96 + x..128 + x]]);
+
+        let mut key = [0u8; 32];
+        key[0..8].copy_from_slice(&sha1_a[0..8]);
+        key[8..20].copy_from_slice(&sha1_b[8..20]);
+        key[20..32].copy_from_slice(&sha1_c[4..16]);
+
+        let mut iv = [0u8; 32];
+        iv[0..12].copy_from_slice(&sha1_a[8..20]);
+        iv[12..20].copy_from_slice(&sha1_b[0..8]);
+        iv[20..24].copy_from_slice(&sha1_c[16..20]);
+        iv[24..32].copy_from_slice(&sha1_d[0..8]);
+
+        (key, iv)
+    }
+}
+
+fn sha1_of(parts: &[&[u8]]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    for part in parts {
+        hasher.input(part);
+    }
+
+    let mut digest = [0u8; 20];
+    hasher.result(&mut digest);
+    digest
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Zero-pad `body` up to the next multiple of 16 bytes for IGE.
+fn pad_to_block(body: &[u8]) -> Vec<u8> {
+    let mut padded = body.to_vec();
+
+    let rem = padded.len() % 16;
+    if rem > 0 {
+        padded.resize(padded.len() + (16 - rem), 0);
+    }
+
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Direction, Session};
+
+    // auth_key[i] = (i * 3 + 1) % 256
+    fn auth_key() -> [u8; 256] {
+        let mut auth_key = [0u8; 256];
+        for (i, byte) in auth_key.iter_mut().enumerate() {
+            *byte = ((i * 3 + 1) % 256) as u8;
+        }
+        auth_key
+    }
+
+    // msg_key[i] = (i * 5 + 2) % 256
+    fn msg_key() -> [u8; 16] {
+        let mut msg_key = [0u8; 16];
+        for (i, byte) in msg_key.iter_mut().enumerate() {
+            *byte = ((i * 5 + 2) % 256) as u8;
+        }
+        msg_key
+    }
+
+    /// Cross-checked against an independent Python implementation of
+    /// the same SHA1-based formula (`hashlib.sha1`).
+    #[test]
+    fn derive_key_iv_matches_known_answer_vector() {
+        let session = Session::new(auth_key());
+
+        let (key, iv) = session.derive_key_iv(&msg_key(), Direction::ClientToServer);
+        assert_eq!(
+            key[..],
+            [
+                0x0b, 0x2c, 0xf5, 0xfe, 0xc2, 0x9b, 0xaf, 0x4e, 0x1b, 0x42, 0x65, 0x97, 0xde,
+                0x1d, 0x64, 0x76, 0x96, 0x89, 0xbc, 0xbd, 0x8f, 0x16, 0x8f, 0x03, 0x5b, 0x6a,
+                0xc0, 0xd3, 0xc6, 0xc4, 0xe0, 0x45,
+            ][..]
+        );
+        assert_eq!(
+            iv[..],
+            [
+                0xc4, 0xec, 0xbf, 0x6f, 0x73, 0x6f, 0x42, 0x9e, 0x0d, 0x25, 0xc9, 0x95, 0xf1,
+                0x6c, 0x2e, 0x4a, 0x1a, 0x11, 0x8a, 0x9f, 0xb1, 0xb8, 0x9b, 0x43, 0xa2, 0xd0,
+                0xe3, 0x11, 0xc0, 0xfd, 0xe2, 0x4d,
+            ][..]
+        );
+
+        let (key, iv) = session.derive_key_iv(&msg_key(), Direction::ServerToClient);
+        assert_eq!(
+            key[..],
+            [
+                0x1d, 0x16, 0xac, 0x01, 0x8c, 0xac, 0x49, 0x23, 0x9a, 0x39, 0xe9, 0xec, 0xc1,
+                0xf6, 0x17, 0x0f, 0xfc, 0x13, 0x4e, 0x4c, 0xe4, 0x31, 0x92, 0xf0, 0xd1, 0xc1,
+                0xa3, 0x80, 0x63, 0xa1, 0x6d, 0x82,
+            ][..]
+        );
+        assert_eq!(
+            iv[..],
+            [
+                0x28, 0x13, 0xd5, 0x1a, 0x0b, 0x1d, 0xeb, 0x2d, 0x26, 0x2d, 0x3b, 0x7b, 0x4e,
+                0x9d, 0xc2, 0xd9, 0x11, 0xcf, 0xd3, 0x0b, 0x39, 0x36, 0x5b, 0xab, 0x04, 0xc0,
+                0xf5, 0xc1, 0x50, 0xaa, 0xf2, 0xad,
+            ][..]
+        );
+    }
+
+    /// `msg_key` must be computed from the unpadded payload, not the
+    /// padded bytes handed to the IGE block cipher; pinning the full
+    /// `encrypt` output against an independent reference catches a
+    /// regression back to hashing the padded payload.
+    #[test]
+    fn encrypt_matches_known_answer_vector_for_unpadded_payload() {
+        let session = Session::new(auth_key());
+
+        // Deliberately not a multiple of 16 bytes.
+        let payload: Vec<u8> = (0..20).map(|i| ((i * 11 + 5) % 256) as u8).collect();
+
+        let encrypted = session.encrypt(&payload).unwrap();
+
+        assert_eq!(
+            encrypted,
+            vec![
+                0xc0, 0x4b, 0x04, 0x87, 0x04, 0xbb, 0x08, 0x1f, 0x6a, 0xc4, 0x47, 0xf6, 0x5b,
+                0x26, 0x89, 0xed, 0x40, 0x5f, 0xf6, 0xb0, 0x57, 0x9b, 0x50, 0xcd, 0xe6, 0x08,
+                0x1c, 0x23, 0x21, 0xb2, 0x29, 0xe9, 0x84, 0x50, 0x12, 0x6b, 0x18, 0x3d, 0x3c,
+                0x46, 0x1f, 0xa3, 0x4f, 0xb6, 0xf5, 0x31, 0xbf, 0x66, 0x6b, 0x0d, 0xe7, 0x0f,
+                0x8b, 0xad, 0x10, 0xd0,
+            ]
+        );
+    }
+}